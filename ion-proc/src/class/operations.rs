@@ -5,7 +5,8 @@
  */
 
 use proc_macro2::Ident;
-use syn::{ItemFn, ItemStatic};
+use syn::punctuated::Punctuated;
+use syn::{Attribute, ItemFn, ItemStatic, Path, Token};
 
 pub(crate) fn class_finalise(class: &Ident) -> ItemFn {
 	let krate = quote!(::ion);
@@ -19,8 +20,41 @@ pub(crate) fn class_finalise(class: &Ident) -> ItemFn {
 	)
 }
 
-pub(crate) fn class_ops() -> ItemStatic {
+pub(crate) fn class_trace(class: &Ident) -> ItemFn {
+	let krate = quote!(::ion);
+	parse_quote!(
+		unsafe extern "C" fn trace_operation(trc: *mut ::mozjs::jsapi::JSTracer, this: *mut ::mozjs::jsapi::JSObject) {
+			let mut value = ::mozjs::jsval::UndefinedValue();
+			::mozjs::glue::JS_GetReservedSlot(this, <#class as #krate::class::ClassInitialiser>::PARENT_PROTOTYPE_CHAIN_LENGTH, &mut value);
+			let private = &*(value.to_private() as *const ::std::option::Option<#class>);
+			if let ::std::option::Option::Some(private) = private {
+				#krate::class::Traceable::trace(private, trc);
+			}
+		}
+	)
+}
+
+/// Detects whether a `#[class]` type opts into GC tracing by deriving `ion::class::Traceable`,
+/// so `class_ops` can wire `trace_operation` in automatically instead of requiring the caller to
+/// track it separately. Classes that hold no rooted JS handles don't derive `Traceable` and keep
+/// `trace: None`, paying no extra cost.
+pub(crate) fn class_is_traceable(attrs: &[Attribute]) -> bool {
+	attrs.iter().any(|attr| {
+		attr.path().is_ident("derive")
+			&& attr
+				.parse_args_with(Punctuated::<Path, Token![,]>::parse_terminated)
+				.map(|paths| paths.iter().any(|path| path.is_ident("Traceable")))
+				.unwrap_or(false)
+	})
+}
+
+pub(crate) fn class_ops(traceable: bool) -> ItemStatic {
 	let none = quote!(::std::option::Option::None);
+	let trace = if traceable {
+		quote!(::std::option::Option::Some(trace_operation))
+	} else {
+		none.clone()
+	};
 	parse_quote!(
 		static OPERATIONS: ::mozjs::jsapi::JSClassOps = ::mozjs::jsapi::JSClassOps {
 			addProperty: #none,
@@ -32,7 +66,50 @@ pub(crate) fn class_ops() -> ItemStatic {
 			finalize: ::std::option::Option::Some(finalise_operation),
 			call: #none,
 			construct: #none,
-			trace: #none,
+			trace: #trace,
 		};
 	)
 }
+
+/// Assembles the finalise operation, the `OPERATIONS` static, and - for classes that opt in via
+/// `#[derive(Traceable)]` - the trace operation, for splicing into a `#[class]` type's generated
+/// impl. This is the single place that decides whether a class is traceable, so `class_ops` is
+/// never called against a stale `traceable` value.
+pub(crate) fn class_operations(class: &Ident, attrs: &[Attribute]) -> (ItemFn, Option<ItemFn>, ItemStatic) {
+	let traceable = class_is_traceable(attrs);
+
+	let finalise = class_finalise(class);
+	let trace = traceable.then(|| class_trace(class));
+	let ops = class_ops(traceable);
+
+	(finalise, trace, ops)
+}
+
+#[cfg(test)]
+mod tests {
+	use syn::parse_quote;
+
+	use super::*;
+
+	#[test]
+	fn class_operations_wires_trace_when_traceable() {
+		let class: Ident = parse_quote!(MyClass);
+		let attrs: Vec<Attribute> = vec![parse_quote!(#[derive(Traceable)])];
+
+		let (_, trace, ops) = class_operations(&class, &attrs);
+
+		assert!(trace.is_some());
+		assert!(quote!(#ops).to_string().contains("trace_operation"));
+	}
+
+	#[test]
+	fn class_operations_omits_trace_when_not_traceable() {
+		let class: Ident = parse_quote!(MyClass);
+		let attrs: Vec<Attribute> = vec![parse_quote!(#[derive(Clone)])];
+
+		let (_, trace, ops) = class_operations(&class, &attrs);
+
+		assert!(trace.is_none());
+		assert!(!quote!(#ops).to_string().contains("trace_operation"));
+	}
+}
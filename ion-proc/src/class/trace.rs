@@ -0,0 +1,47 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+use proc_macro2::TokenStream;
+use syn::{Data, DeriveInput, Error, Fields, Index, Result};
+
+/// Generates the body of `#[derive(Traceable)]`: an `unsafe impl` that forwards every field of
+/// the annotated struct to `Traceable::trace`, so deriving it is enough for a native class that
+/// embeds live JS values to become GC-safe without hand-writing the trace walk.
+pub(crate) fn impl_traceable(input: &DeriveInput) -> Result<TokenStream> {
+	let krate = quote!(::ion);
+	let ident = &input.ident;
+	let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+	let Data::Struct(data) = &input.data else {
+		return Err(Error::new_spanned(input, "#[derive(Traceable)] only supports structs"));
+	};
+
+	let fields: Vec<TokenStream> = match &data.fields {
+		Fields::Named(fields) => fields
+			.named
+			.iter()
+			.map(|field| {
+				let ident = field.ident.as_ref().unwrap();
+				quote!(unsafe { #krate::class::Traceable::trace(&self.#ident, trc) };)
+			})
+			.collect(),
+		Fields::Unnamed(fields) => (0..fields.unnamed.len())
+			.map(|index| {
+				let index = Index::from(index);
+				quote!(unsafe { #krate::class::Traceable::trace(&self.#index, trc) };)
+			})
+			.collect(),
+		Fields::Unit => Vec::new(),
+	};
+
+	Ok(quote!(
+		unsafe impl #impl_generics #krate::class::Traceable for #ident #ty_generics #where_clause {
+			unsafe fn trace(&self, trc: *mut ::mozjs::jsapi::JSTracer) {
+				#(#fields)*
+			}
+		}
+	))
+}
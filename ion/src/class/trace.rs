@@ -0,0 +1,58 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+use mozjs::jsapi::JSTracer;
+
+use crate::{Object, Promise, Value};
+
+/// Implemented by types embedded in a native [class](crate::class::ClassInitialiser) that hold
+/// rooted JS handles (`Object`, `Value`, `Promise`, and `Option`s or `Vec`s of them), so the garbage collector
+/// can see them across a collection instead of reclaiming them out from under the native object.
+///
+/// `#[derive(Traceable)]` implements this by tracing every field of the annotated struct in turn;
+/// a field whose type has no live JS handles (`u32`, `String`, ...) needs no `Traceable` impl of
+/// its own as long as the struct doesn't derive over it, but composite fields that do hold one
+/// only need their own `Traceable` impl (or derive) to be picked up automatically.
+pub trait Traceable {
+	/// Forwards every JS handle held by `self` to `trc`, keeping them alive across garbage
+	/// collection. Implementations must visit every held handle; skipping one is unsound, since
+	/// the collector may then free memory the native object still points to.
+	unsafe fn trace(&self, trc: *mut JSTracer);
+}
+
+impl<T: Traceable> Traceable for Option<T> {
+	unsafe fn trace(&self, trc: *mut JSTracer) {
+		if let Some(value) = self {
+			unsafe { value.trace(trc) };
+		}
+	}
+}
+
+impl<T: Traceable> Traceable for Vec<T> {
+	unsafe fn trace(&self, trc: *mut JSTracer) {
+		for value in self {
+			unsafe { value.trace(trc) };
+		}
+	}
+}
+
+impl Traceable for Object<'_> {
+	unsafe fn trace(&self, trc: *mut JSTracer) {
+		unsafe { mozjs::glue::CallObjectTracer(trc, self.handle().into(), c"object".as_ptr()) };
+	}
+}
+
+impl Traceable for Value<'_> {
+	unsafe fn trace(&self, trc: *mut JSTracer) {
+		unsafe { mozjs::glue::CallValueTracer(trc, self.handle().into(), c"value".as_ptr()) };
+	}
+}
+
+impl Traceable for Promise<'_> {
+	unsafe fn trace(&self, trc: *mut JSTracer) {
+		unsafe { self.as_object().trace(trc) };
+	}
+}
@@ -0,0 +1,106 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/.
+ */
+
+use colored::Color;
+
+/// The colours used for each kind of value when formatting with colour output enabled.
+#[derive(Clone, Copy, Debug)]
+pub struct Colours {
+	pub object: Color,
+	pub number: Color,
+	pub string: Color,
+	pub boolean: Color,
+	pub other: Color,
+}
+
+impl Default for Colours {
+	fn default() -> Colours {
+		Colours {
+			object: Color::White,
+			number: Color::Yellow,
+			string: Color::Green,
+			boolean: Color::Yellow,
+			other: Color::White,
+		}
+	}
+}
+
+/// Configuration threaded through every formatter in [format](crate::format), controlling depth,
+/// layout and colouring. Cheap to copy, so nested formatting calls can derive a tweaked copy (see
+/// [Config::depth], [Config::quoted]) without affecting the caller's.
+#[derive(Clone, Copy, Debug)]
+pub struct Config {
+	pub colours: Colours,
+	pub indentation: u16,
+	pub depth: u16,
+	pub iteration: u32,
+	pub multiline: bool,
+	pub quoted: bool,
+	/// The column budget used, when `multiline` is off, to decide whether a plain object's, map's
+	/// or set's entries fit on a single line, mirroring `util.inspect`'s `breakLength` option.
+	/// Array layout does not consult it.
+	pub break_length: usize,
+	/// Whether an accessor property is rendered by evaluating its getter and showing the result as
+	/// `[Getter: <value>]`, rather than just `[Getter]`.
+	pub get_accessors: bool,
+}
+
+impl Default for Config {
+	fn default() -> Config {
+		Config {
+			colours: Colours::default(),
+			indentation: 0,
+			depth: 0,
+			iteration: 0,
+			multiline: true,
+			quoted: false,
+			break_length: 80,
+			get_accessors: false,
+		}
+	}
+}
+
+impl Config {
+	pub fn colours(mut self, colours: Colours) -> Config {
+		self.colours = colours;
+		self
+	}
+
+	pub fn indentation(mut self, indentation: u16) -> Config {
+		self.indentation = indentation;
+		self
+	}
+
+	pub fn depth(mut self, depth: u16) -> Config {
+		self.depth = depth;
+		self
+	}
+
+	pub fn iteration(mut self, iteration: u32) -> Config {
+		self.iteration = iteration;
+		self
+	}
+
+	pub fn multiline(mut self, multiline: bool) -> Config {
+		self.multiline = multiline;
+		self
+	}
+
+	pub fn quoted(mut self, quoted: bool) -> Config {
+		self.quoted = quoted;
+		self
+	}
+
+	pub fn break_length(mut self, break_length: usize) -> Config {
+		self.break_length = break_length;
+		self
+	}
+
+	pub fn get_accessors(mut self, get_accessors: bool) -> Config {
+		self.get_accessors = get_accessors;
+		self
+	}
+}
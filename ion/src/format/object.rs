@@ -4,15 +4,16 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/.
  */
 
+use std::cell::RefCell;
 use std::cmp::Ordering;
 use std::fmt;
 use std::fmt::{Display, Formatter, Write};
 
 use colored::{Color, Colorize};
 use itoa::Buffer;
-use mozjs::jsapi::{ESClass, Type};
+use mozjs::jsapi::{ESClass, JSObject, Type};
 
-use crate::{Array, Context, Date, Exception, Function, Object, Promise, PropertyKey, RegExp, Value};
+use crate::{Array, Context, Date, Exception, Function, Map, Object, Promise, PropertyKey, RegExp, Set, Symbol, Value};
 use crate::conversions::ToValue;
 use crate::format::{format_value, INDENT, NEWLINE};
 use crate::format::array::format_array;
@@ -26,8 +27,8 @@ use crate::format::promise::format_promise;
 use crate::format::regexp::format_regexp;
 use crate::format::typedarray::{format_array_buffer, format_typed_array};
 use crate::typedarray::{
-	ArrayBuffer, ArrayBufferView, ClampedUint8Array, Float32Array, Float64Array, Int16Array, Int32Array, Int8Array,
-	Uint16Array, Uint32Array, Uint8Array,
+	ArrayBuffer, ArrayBufferView, BigInt64Array, BigUint64Array, ClampedUint8Array, DataView, Float32Array,
+	Float64Array, Int16Array, Int32Array, Int8Array, Uint16Array, Uint32Array, Uint8Array,
 };
 
 /// Formats a [JavaScript Object](Object), depending on its class, as a string using the given [configuration](Config).
@@ -50,17 +51,19 @@ impl Display for ObjectDisplay<'_> {
 		let cfg = self.cfg;
 		let object = Object::from(cx.root_object(self.object.handle().get()));
 
+		if let Some(custom) = call_custom_inspect(cx, cfg, &self.object) {
+			return f.write_str(&custom);
+		}
+
 		let class = self.object.get_builtin_class(cx);
 
 		match class {
 			ESC::Boolean | ESC::Number | ESC::String | ESC::BigInt => {
 				write!(f, "{}", format_boxed(cx, cfg, &self.object))
 			}
-			ESC::Array => write!(
-				f,
-				"{}",
-				format_array(cx, cfg, &Array::from(cx, object.into_local()).unwrap())
-			),
+			ESC::Array => with_visited(cfg, self.object.handle().get(), f, |f| {
+				write!(f, "{}", format_array(cx, cfg, &Array::from(cx, object.into_local()).unwrap()))
+			}),
 			ESC::Date => write!(
 				f,
 				"{}",
@@ -86,19 +89,25 @@ impl Display for ObjectDisplay<'_> {
 				"{}",
 				format_array_buffer(cfg, &ArrayBuffer::from(object.into_local()).unwrap())
 			),
+			ESC::Map => with_visited(cfg, self.object.handle().get(), f, |f| {
+				write!(f, "{}", format_map(cx, cfg, &Map::from(cx, object.into_local()).unwrap()))
+			}),
+			ESC::Set => with_visited(cfg, self.object.handle().get(), f, |f| {
+				write!(f, "{}", format_set(cx, cfg, &Set::from(cx, object.into_local()).unwrap()))
+			}),
 			ESC::Error => match Exception::from_object(cx, &self.object) {
 				Exception::Error(error) => f.write_str(&error.format()),
 				_ => unreachable!("Expected Error"),
 			},
-			ESC::Object => {
-				write!(
-					f,
-					"{}",
-					format_plain_object(cx, cfg, &Object::from(object.into_local()))
-				)
-			}
+			ESC::Object => with_visited(cfg, self.object.handle().get(), f, |f| {
+				write!(f, "{}", format_plain_object(cx, cfg, &Object::from(object.into_local())))
+			}),
 			ESC::Other => {
 				if let Some(view) = ArrayBufferView::from(cx.root_object(object.handle().get())) {
+					if let Some(data_view) = DataView::from(cx.root_object(view.handle().get())) {
+						return write!(f, "{}", format_data_view(cfg, &data_view));
+					}
+
 					'view: {
 						return match view.view_type() {
 							Type::Int8 => write!(
@@ -146,6 +155,16 @@ impl Display for ObjectDisplay<'_> {
 								"{}",
 								format_typed_array(cfg, &ClampedUint8Array::from(view.into_local()).unwrap())
 							),
+							Type::BigInt64 => write!(
+								f,
+								"{}",
+								format_typed_array(cfg, &BigInt64Array::from(view.into_local()).unwrap())
+							),
+							Type::BigUint64 => write!(
+								f,
+								"{}",
+								format_typed_array(cfg, &BigUint64Array::from(view.into_local()).unwrap())
+							),
 							_ => break 'view,
 						};
 					}
@@ -161,6 +180,115 @@ impl Display for ObjectDisplay<'_> {
 	}
 }
 
+/// Looks up the `ion.inspect.custom` symbol, obtained from the global symbol registry so that
+/// script can install this hook via `Symbol.for("ion.inspect.custom")`, on `object` and, if it
+/// resolves to a callable, invokes it with an options object describing the current formatting
+/// depth, colour mode and whether the output is multiline, splicing the returned string directly
+/// into the output instead of falling back to [format_class_object] or [format_plain_object].
+/// This lets native and script-defined classes control their own `console.log` representation.
+fn call_custom_inspect(cx: &Context, cfg: Config, object: &Object) -> Option<String> {
+	let symbol = Symbol::for_(cx, "ion.inspect.custom");
+	let key = PropertyKey::with_symbol(cx, &symbol);
+	let value = object.get(cx, &key)?;
+
+	let function = Function::from_value(cx, &value).ok()?;
+
+	let options = Object::new(cx);
+	options.set_as(cx, "depth", &cfg.depth);
+	options.set_as(cx, "multiline", &cfg.multiline);
+	options.set_as(cx, "colors", &colored::control::SHOULD_COLORIZE.should_colorize());
+
+	let result = function.call(cx, object, &[options.as_value(cx)]).ok()?;
+	Some(result.to_string(cx))
+}
+
+/// Formats a [DataView] as a string using the given [configuration](Config), reporting its byte
+/// length and offset into its backing buffer rather than being rendered as an opaque object.
+fn format_data_view(cfg: Config, view: &DataView) -> DataViewDisplay {
+	DataViewDisplay { view, cfg }
+}
+
+struct DataViewDisplay<'d> {
+	view: &'d DataView,
+	cfg: Config,
+}
+
+impl Display for DataViewDisplay<'_> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		let colour = self.cfg.colours.object;
+		let bytes = self.view.bytes();
+
+		write!(f, "{}", format!("DataView({})", bytes.len()).color(colour))?;
+		write!(f, "{}", " [".color(colour))?;
+
+		if bytes.is_empty() {
+			return write!(f, "{}", "]".color(colour));
+		}
+
+		f.write_char(' ')?;
+		if self.view.byte_offset() != 0 {
+			write!(f, "{}", format!("byteOffset: {}, ", self.view.byte_offset()).color(colour))?;
+		}
+
+		let len = bytes.len().clamp(0, 3);
+		for (i, byte) in bytes.iter().take(len).enumerate() {
+			write!(f, "{}", byte.to_string().color(self.cfg.colours.number))?;
+
+			if i != len - 1 {
+				write!(f, "{}", ",".color(colour))?;
+				f.write_char(' ')?;
+			}
+		}
+
+		let remaining = bytes.len() - len;
+		write_remaining(f, remaining, None, colour)?;
+		if remaining == 0 {
+			f.write_char(' ')?;
+		}
+
+		write!(f, "{}", "]".color(colour))
+	}
+}
+
+thread_local! {
+	/// The stack of object pointers currently being formatted on this thread, used by
+	/// [with_visited] to detect cycles. `Config` is `Copy` and threaded through every formatter
+	/// by value, so the visited stack can't live on it without losing that; a thread-local avoids
+	/// needing to plumb an extra parameter through every formatting function in this module and
+	/// the sibling ones ([format_array], [format_value]) that recurse back into [format_object].
+	static VISITED: RefCell<Vec<*mut JSObject>> = RefCell::new(Vec::new());
+}
+
+/// Guards against infinite recursion when formatting self-referential objects and arrays.
+///
+/// Pushes `ptr` onto [VISITED] before running `body`. If `ptr` is already on the stack,
+/// `body` is skipped entirely and `[Circular *n]` is written instead, where `n` is the depth at
+/// which the object was first visited. `ptr` is popped again once `body` returns, including when
+/// it returns an [Err](fmt::Error), so sibling positions can still format the same object
+/// independently.
+fn with_visited(
+	cfg: Config, ptr: *mut JSObject, f: &mut Formatter, body: impl FnOnce(&mut Formatter) -> fmt::Result,
+) -> fmt::Result {
+	let index = VISITED.with(|visited| {
+		let mut visited = visited.borrow_mut();
+		match visited.iter().position(|visited| *visited == ptr) {
+			Some(index) => Some(index),
+			None => {
+				visited.push(ptr);
+				None
+			}
+		}
+	});
+
+	if let Some(index) = index {
+		return write!(f, "{}", format!("[Circular *{}]", index + 1).color(cfg.colours.object));
+	}
+
+	let result = body(f);
+	VISITED.with(|visited| visited.borrow_mut().pop());
+	result
+}
+
 /// Formats a [JavaScript Object](Object) as a string using the given [configuration](Config).
 /// Disregards the class of the object.
 pub fn format_plain_object<'cx>(cx: &'cx Context, cfg: Config, object: &'cx Object<'cx>) -> PlainObjectDisplay<'cx> {
@@ -179,60 +307,273 @@ impl Display for PlainObjectDisplay<'_> {
 
 		if self.cfg.depth < 4 {
 			let keys = self.object.keys(self.cx, Some(self.cfg.iteration));
-			let length = keys.len();
 
-			if length == 0 {
-				write!(f, "{}", "{}".color(colour))
+			if keys.len() == 0 {
+				return write!(f, "{}", "{}".color(colour));
+			}
+
+			let entries: Vec<_> = keys
+				.map(|key| key_value_string(self.cx, self.cfg, self.object, &key))
+				.collect();
+
+			write!(f, "{}", "{".color(colour))?;
+
+			let indent = (self.cfg.indentation + self.cfg.depth) as usize;
+			if !self.cfg.multiline && fits_on_one_line(&entries, self.cfg.break_length, indent) {
+				f.write_char(' ')?;
+				write_entries_inline(f, &entries, colour)?;
+				f.write_char(' ')?;
 			} else {
-				write!(f, "{}", "{".color(colour))?;
+				f.write_str(NEWLINE)?;
+				let inner = INDENT.repeat(indent + 1);
 
-				if self.cfg.multiline {
+				for entry in &entries {
+					f.write_str(&inner)?;
+					f.write_str(entry)?;
+					write!(f, "{}", ",".color(colour))?;
 					f.write_str(NEWLINE)?;
-					let inner = INDENT.repeat((self.cfg.indentation + self.cfg.depth + 1) as usize);
-
-					for key in keys {
-						f.write_str(&inner)?;
-						let value = self.object.get(self.cx, &key).unwrap();
-						write_key_value(f, self.cx, self.cfg, &key, &value)?;
-						write!(f, "{}", ",".color(colour))?;
-						f.write_str(NEWLINE)?;
-					}
+				}
 
-					f.write_str(&INDENT.repeat((self.cfg.indentation + self.cfg.depth) as usize))?;
-				} else {
-					f.write_char(' ')?;
-					let len = length.clamp(0, 3);
+				f.write_str(&INDENT.repeat(indent))?;
+			}
 
-					for (i, key) in keys.enumerate() {
-						let value = self.object.get(self.cx, &key).unwrap();
-						write_key_value(f, self.cx, self.cfg, &key, &value)?;
+			write!(f, "{}", "}".color(colour))
+		} else {
+			write!(f, "{}", "[Object]".color(colour))
+		}
+	}
+}
 
-						if i != len - 1 {
-							write!(f, "{}", ",".color(colour))?;
-							f.write_char(' ')?;
-						}
-					}
+fn write_entries_inline(f: &mut Formatter, entries: &[String], colour: Color) -> fmt::Result {
+	let len = entries.len();
+	for (i, entry) in entries.iter().enumerate() {
+		f.write_str(entry)?;
+		if i != len - 1 {
+			write!(f, "{}", ",".color(colour))?;
+			f.write_char(' ')?;
+		}
+	}
+	Ok(())
+}
 
-					let remaining = length - len;
-					write_remaining(f, remaining, None, colour)?;
-				}
+/// Decides whether `entries`, already rendered to their final display text, fit within
+/// `break_length` columns once `indent` levels of [INDENT] and the surrounding `{ ` / ` }` and
+/// `, ` separators are accounted for. Mirrors `util.inspect`'s `breakLength` option: widths are
+/// measured by [display_width], with ANSI colour codes excluded so colourised output isn't
+/// penalised for escape sequences that occupy no visible space.
+fn fits_on_one_line(entries: &[String], break_length: usize, indent: usize) -> bool {
+	if entries.iter().any(|entry| entry.contains('\n')) {
+		return false;
+	}
 
-				write!(f, "{}", "}".color(colour))
+	let budget = break_length.saturating_sub(indent * INDENT.len());
+	let separators = entries.len().saturating_sub(1) * 2;
+	let content: usize = entries.iter().map(|entry| display_width(entry)).sum();
+
+	content + separators + "{  }".len() <= budget
+}
+
+/// Measures the width of `s` as its number of `char`s, skipping over ANSI colour escape sequences
+/// so they don't count towards layout decisions. Every `char` counts as one column: wide
+/// characters and combining marks are not measured by the columns they occupy on screen.
+fn display_width(s: &str) -> usize {
+	let mut width = 0;
+	let mut in_escape = false;
+
+	for ch in s.chars() {
+		if in_escape {
+			if ch == 'm' {
+				in_escape = false;
 			}
+		} else if ch == '\u{1b}' {
+			in_escape = true;
 		} else {
-			write!(f, "{}", "[Object]".color(colour))
+			width += 1;
+		}
+	}
+
+	width
+}
+
+/// Formats a [JavaScript Map](Map) as a string using the given [configuration](Config).
+pub fn format_map<'cx>(cx: &'cx Context, cfg: Config, map: &'cx Map<'cx>) -> MapDisplay<'cx> {
+	MapDisplay { cx, map, cfg }
+}
+
+pub struct MapDisplay<'cx> {
+	cx: &'cx Context,
+	map: &'cx Map<'cx>,
+	cfg: Config,
+}
+
+impl Display for MapDisplay<'_> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		let colour = self.cfg.colours.object;
+		let size = self.map.size(self.cx);
+
+		write!(f, "{}", format!("Map({size})").color(colour))?;
+
+		if size == 0 {
+			return write!(f, "{}", " {}".color(colour));
+		}
+		write!(f, "{}", " {".color(colour))?;
+
+		let inner_cfg = self.cfg.depth(self.cfg.depth + 1).quoted(true);
+		let entries: Vec<String> = self
+			.map
+			.entries(self.cx)
+			.take(self.cfg.iteration as usize)
+			.map(|(key, value)| {
+				format!(
+					"{} {} {}",
+					format_value(self.cx, inner_cfg, &key),
+					"=>".color(colour),
+					format_value(self.cx, inner_cfg, &value)
+				)
+			})
+			.collect();
+		let remaining = size as usize - entries.len();
+
+		let indent = (self.cfg.indentation + self.cfg.depth) as usize;
+		if remaining == 0 && !self.cfg.multiline && fits_on_one_line(&entries, self.cfg.break_length, indent) {
+			f.write_char(' ')?;
+			write_entries_inline(f, &entries, colour)?;
+			f.write_char(' ')?;
+		} else {
+			f.write_str(NEWLINE)?;
+			let inner = INDENT.repeat(indent + 1);
+
+			for entry in &entries {
+				f.write_str(&inner)?;
+				f.write_str(entry)?;
+				write!(f, "{}", ",".color(colour))?;
+				f.write_str(NEWLINE)?;
+			}
+
+			if remaining > 0 {
+				f.write_str(&inner)?;
+				write_remaining(f, remaining, None, colour)?;
+				f.write_str(NEWLINE)?;
+			}
+
+			f.write_str(&INDENT.repeat(indent))?;
 		}
+
+		write!(f, "{}", "}".color(colour))
+	}
+}
+
+/// Formats a [JavaScript Set](Set) as a string using the given [configuration](Config).
+pub fn format_set<'cx>(cx: &'cx Context, cfg: Config, set: &'cx Set<'cx>) -> SetDisplay<'cx> {
+	SetDisplay { cx, set, cfg }
+}
+
+pub struct SetDisplay<'cx> {
+	cx: &'cx Context,
+	set: &'cx Set<'cx>,
+	cfg: Config,
+}
+
+impl Display for SetDisplay<'_> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		let colour = self.cfg.colours.object;
+		let size = self.set.size(self.cx);
+
+		write!(f, "{}", format!("Set({size})").color(colour))?;
+
+		if size == 0 {
+			return write!(f, "{}", " {}".color(colour));
+		}
+		write!(f, "{}", " {".color(colour))?;
+
+		let inner_cfg = self.cfg.depth(self.cfg.depth + 1).quoted(true);
+		let values: Vec<String> = self
+			.set
+			.values(self.cx)
+			.take(self.cfg.iteration as usize)
+			.map(|value| format!("{}", format_value(self.cx, inner_cfg, &value)))
+			.collect();
+		let remaining = size as usize - values.len();
+
+		let indent = (self.cfg.indentation + self.cfg.depth) as usize;
+		if remaining == 0 && !self.cfg.multiline && fits_on_one_line(&values, self.cfg.break_length, indent) {
+			f.write_char(' ')?;
+			write_entries_inline(f, &values, colour)?;
+			f.write_char(' ')?;
+		} else {
+			f.write_str(NEWLINE)?;
+			let inner = INDENT.repeat(indent + 1);
+
+			for value in &values {
+				f.write_str(&inner)?;
+				f.write_str(value)?;
+				write!(f, "{}", ",".color(colour))?;
+				f.write_str(NEWLINE)?;
+			}
+
+			if remaining > 0 {
+				f.write_str(&inner)?;
+				write_remaining(f, remaining, None, colour)?;
+				f.write_str(NEWLINE)?;
+			}
+
+			f.write_str(&INDENT.repeat(indent))?;
+		}
+
+		write!(f, "{}", "}".color(colour))
 	}
 }
 
-fn write_key_value(f: &mut Formatter, cx: &Context, cfg: Config, key: &PropertyKey, value: &Value) -> fmt::Result {
-	write!(
-		f,
+fn key_value_string(cx: &Context, cfg: Config, object: &Object, key: &PropertyKey) -> String {
+	let rendered = match accessor_marker(cx, cfg, object, key) {
+		Some(marker) => marker,
+		None => {
+			let value = object.get(cx, key).unwrap();
+			format!("{}", format_value(cx, cfg.depth(cfg.depth + 1).quoted(true), &value))
+		}
+	};
+
+	let mut buf = String::new();
+	let _ = write!(
+		buf,
 		"{}{} {}",
 		format_key(cx, cfg, &key.to_owned_key(cx)),
 		":".color(cfg.colours.object),
-		format_value(cx, cfg.depth(cfg.depth + 1).quoted(true), value)
-	)
+		rendered
+	);
+	buf
+}
+
+/// Renders accessor properties as `[Getter]`, `[Setter]`, or `[Getter/Setter]` instead of
+/// invoking the getter, since merely formatting an object shouldn't be able to run arbitrary
+/// script or mutate program state. Returns [None] for data properties, which are rendered
+/// normally by the caller. When [Config::get_accessors] opts in, the getter is evaluated and its
+/// result shown as `[Getter: <value>]`.
+fn accessor_marker(cx: &Context, cfg: Config, object: &Object, key: &PropertyKey) -> Option<String> {
+	let descriptor = object.get_own_property_descriptor(cx, key)?;
+	if !descriptor.is_accessor_descriptor() {
+		return None;
+	}
+
+	let colour = cfg.colours.object;
+	let has_getter = descriptor.getter(cx).is_some();
+	let has_setter = descriptor.setter(cx).is_some();
+
+	if has_getter && cfg.get_accessors {
+		let value = object.get(cx, key).unwrap();
+		let value = format_value(cx, cfg.depth(cfg.depth + 1).quoted(true), &value);
+		return Some(format!("[Getter: {value}]").color(colour).to_string());
+	}
+
+	// An accessor descriptor can have neither a getter nor a setter (e.g. both explicitly defined
+	// as `undefined`); reading it never invokes anything, so it's rendered like a normal property.
+	let marker = match (has_getter, has_setter) {
+		(true, true) => "[Getter/Setter]",
+		(true, false) => "[Getter]",
+		(false, true) => "[Setter]",
+		(false, false) => return None,
+	};
+	Some(marker.color(colour).to_string())
 }
 
 pub(crate) fn write_remaining(f: &mut Formatter, remaining: usize, inner: Option<&str>, colour: Color) -> fmt::Result {